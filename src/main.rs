@@ -113,6 +113,27 @@ pub enum Command {
         /// Error message
         message: String,
     },
+    /// Set a notice message
+    ///
+    /// Creates a notice message and prints the message to the log. You can optionally provide a
+    /// filename (file), line/column range (line, col, end-line, end-column), and a title for
+    /// where the notice applies.
+    Notice {
+        #[structopt(short, long)]
+        file: Option<String>,
+        #[structopt(short, long)]
+        line: Option<u64>,
+        #[structopt(short, long, alias = "column", name = "column")]
+        col: Option<u64>,
+        #[structopt(long = "end-line")]
+        end_line: Option<u64>,
+        #[structopt(long = "end-column")]
+        end_column: Option<u64>,
+        #[structopt(short, long)]
+        title: Option<String>,
+        /// Notice message
+        message: String,
+    },
     /// Mask a value in log
     ///
     /// Masking a value prevents a string or variable from being printed in the log. Each masked
@@ -138,6 +159,13 @@ pub enum Command {
         name: String,
         #[structopt(short, long)]
         required: bool,
+        /// Parse the input as a boolean using the YAML 1.2 Core Schema truthy set
+        /// (`true`/`True`/`TRUE`, `false`/`False`/`FALSE`), erroring on anything else
+        #[structopt(long = "bool")]
+        as_bool: bool,
+        /// Split the input on newlines and emit each non-empty line on its own output line
+        #[structopt(long)]
+        multiline: bool,
     },
     /// Begin an output group.
     ///
@@ -164,10 +192,71 @@ pub enum Command {
     PostComment {
         /// The content of comment message
         message: String,
-        /// The secret name for authorization. GITHUB_TOKEN is used by default.
+        /// The name of the env var holding the authorization token. GITHUB_TOKEN is used by
+        /// default.
         #[structopt(short="t", long="token", default_value = "GITHUB_TOKEN")]
         secret: String,
+        /// Hidden marker embedded in the comment body. When a prior comment by `--login` already
+        /// carries the marker, it is updated in place instead of posting a new comment.
+        #[structopt(short, long)]
+        marker: Option<String>,
+        /// Login of the comment author to match when looking for an existing marked comment.
+        /// The default `GITHUB_TOKEN` authenticates as this bot, not as a user, so there is no
+        /// `/user` endpoint to query for its identity.
+        #[structopt(long, default_value = "github-actions[bot]")]
+        login: String,
+    },
+    /// Load a `.env` file and export every entry to `GITHUB_ENV`.
+    ///
+    /// Lets a project keep its configuration in a checked-in `.env` file and have action-cli
+    /// hydrate the job environment in one call instead of many `set-env` invocations.
+    LoadEnv {
+        /// Path to the dotenv file to load
+        file: String,
     },
+    /// Fail the current step.
+    ///
+    /// Prints an error annotation with the given message and exits the process with a non-zero
+    /// status code, matching `setFailed` in actions-core.
+    SetFailed {
+        /// Error message
+        message: String,
+    },
+    /// Append Markdown to the job summary (`GITHUB_STEP_SUMMARY`), rendered on the run page.
+    ///
+    /// The body comes from the positional argument, `--file`, or `--stdin` (in that order of
+    /// precedence). `--heading <level>` wraps the body as a Markdown heading instead of writing
+    /// it verbatim, and `--header`/`--row` build a GitHub-flavored Markdown table instead of
+    /// using the body at all.
+    Summary {
+        /// Markdown body to write
+        body: Option<String>,
+        /// Read the body from this file instead of the positional argument
+        #[structopt(long)]
+        file: Option<String>,
+        /// Read the body from stdin instead of the positional argument
+        #[structopt(long)]
+        stdin: bool,
+        /// Truncate the summary file before writing instead of appending to it
+        #[structopt(long)]
+        overwrite: bool,
+        /// Wrap the body as a Markdown heading of this level (1-6)
+        #[structopt(long)]
+        heading: Option<u8>,
+        /// Column headers; when given, writes a Markdown table instead of the body
+        #[structopt(long)]
+        header: Vec<String>,
+        /// A comma-separated table row; repeat `--row` for multiple rows
+        #[structopt(long)]
+        row: Vec<String>,
+    },
+    /// Read newline-delimited command invocations from stdin and execute each one in sequence.
+    ///
+    /// Each line is parsed with the same grammar as the top-level subcommands and produces one
+    /// output line, so a script can compose many annotations/outputs/masks in a single process
+    /// instead of invoking the binary once per command. `stop-commands`/`issue-command` and
+    /// `add-mask` state persists across lines within the batch.
+    Batch,
 }
 
 #[derive(StructOpt, Debug)]
@@ -176,6 +265,223 @@ struct Opt {
     command: Command,
 }
 
+/// Generate a delimiter token for the heredoc form of environment-file entries.
+///
+/// This doesn't need to be cryptographically random, only unlikely enough to collide with a
+/// value that callers ever write. `write_env_file` re-rolls it if it does collide.
+fn random_delimiter() -> String {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("ghadelimiter_{:x}{:x}", nanos, std::process::id())
+}
+
+/// Append a `key=value` entry to one of the `GITHUB_*` environment files.
+///
+/// Falls back to the heredoc form (`key<<DELIM`, the value, then `DELIM`) whenever the value
+/// contains a newline, since the plain `key=value` form can't represent one.
+fn write_env_file<T: AsRef<str>>(path: T, key: &str, value: &str) -> Result<(), Box<dyn Error>> {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path.as_ref())?;
+    if value.contains('\n') {
+        let delimiter = loop {
+            let candidate = random_delimiter();
+            if !value.contains(&candidate) {
+                break candidate;
+            }
+        };
+        writeln!(file, "{}<<{}", key, delimiter)?;
+        writeln!(file, "{}", value)?;
+        writeln!(file, "{}", delimiter)?;
+    } else {
+        writeln!(file, "{}={}", key, value)?;
+    }
+    Ok(())
+}
+
+/// Parse a `.env` file into an ordered list of `(key, value)` pairs.
+///
+/// Supports `KEY=value` lines, an optional `export ` prefix, `#` comments, blank lines,
+/// single- and double-quoted values (with escape handling inside double quotes), and
+/// backslash line continuations.
+fn parse_dotenv(contents: &str) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let mut pairs = Vec::new();
+    let mut lines = contents.lines();
+
+    while let Some(raw_line) = lines.next() {
+        let mut line = raw_line.to_owned();
+        while line.ends_with('\\') {
+            line.pop();
+            match lines.next() {
+                Some(next) => line.push_str(next),
+                None => break,
+            }
+        }
+
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        let trimmed = trimmed.strip_prefix("export ").unwrap_or(trimmed).trim_start();
+
+        let pos = trimmed
+            .find('=')
+            .ok_or_else(|| format!("invalid dotenv line, missing `=`: `{}`", raw_line))?;
+        let key = trimmed[..pos].trim().to_owned();
+        let raw_value = trimmed[pos + 1..].trim();
+
+        let value = if raw_value.len() >= 2 && raw_value.starts_with('\'') && raw_value.ends_with('\'') {
+            raw_value[1..raw_value.len() - 1].to_owned()
+        } else if raw_value.len() >= 2 && raw_value.starts_with('"') && raw_value.ends_with('"') {
+            unescape_double_quoted(&raw_value[1..raw_value.len() - 1])
+        } else {
+            raw_value.to_owned()
+        };
+
+        pairs.push((key, value));
+    }
+
+    Ok(pairs)
+}
+
+/// Resolve backslash escapes (`\n`, `\r`, `\t`, `\"`, `\\`) inside a double-quoted dotenv value.
+fn unescape_double_quoted(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('r') => out.push('\r'),
+                Some('t') => out.push('\t'),
+                Some('"') => out.push('"'),
+                Some('\\') => out.push('\\'),
+                Some(other) => {
+                    out.push('\\');
+                    out.push(other);
+                }
+                None => out.push('\\'),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Split a batch line into argv tokens using shell-like whitespace/quote rules.
+///
+/// Supports single- and double-quoted tokens (with backslash escapes inside double quotes and
+/// outside of quotes) so a `batch` line can carry spaces and quoting the same way a shell
+/// invocation of the top-level subcommands would.
+fn split_command_line(line: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut chars = line.chars();
+    let mut in_single = false;
+    let mut in_double = false;
+    let mut has_token = false;
+
+    while let Some(c) = chars.next() {
+        if in_single {
+            if c == '\'' {
+                in_single = false;
+            } else {
+                current.push(c);
+            }
+        } else if in_double {
+            if c == '"' {
+                in_double = false;
+            } else if c == '\\' {
+                current.push(chars.next().unwrap_or('\\'));
+            } else {
+                current.push(c);
+            }
+        } else {
+            match c {
+                ' ' | '\t' => {
+                    if has_token {
+                        tokens.push(std::mem::take(&mut current));
+                        has_token = false;
+                    }
+                }
+                '\'' => {
+                    in_single = true;
+                    has_token = true;
+                }
+                '"' => {
+                    in_double = true;
+                    has_token = true;
+                }
+                '\\' => {
+                    if let Some(next) = chars.next() {
+                        current.push(next);
+                        has_token = true;
+                    }
+                }
+                _ => {
+                    current.push(c);
+                    has_token = true;
+                }
+            }
+        }
+    }
+
+    if in_single || in_double {
+        return Err(format!("unterminated quote in batch line: `{}`", line).into());
+    }
+    if has_token {
+        tokens.push(current);
+    }
+
+    Ok(tokens)
+}
+
+/// Redact every registered `add-mask` value in `text`, matching `stop-commands`/`add-mask`
+/// semantics: masks accumulate across a batch and apply to every line dispatched afterward.
+fn apply_masks(text: &str, masks: &[String]) -> String {
+    let mut result = text.to_owned();
+    for mask in masks {
+        if !mask.is_empty() {
+            result = result.replace(mask.as_str(), "***");
+        }
+    }
+    result
+}
+
+/// Whether a line parsed while `stop-commands` is in effect resumes normal dispatch, i.e. it is
+/// the matching `::endtoken::` issue command rather than arbitrary passthrough output.
+fn is_resume_line(stopped_token: &str, parsed: &Option<Command>) -> bool {
+    matches!(parsed, Some(Command::IssueCommand { command, .. }) if command == stopped_token)
+}
+
+/// Render a GitHub-flavored Markdown table from a header row and comma-separated data rows.
+fn render_markdown_table(header: &[String], rows: &[String]) -> String {
+    let mut out = format!("| {} |\n", header.join(" | "));
+    out.push_str(&format!("|{}|\n", vec!["---"; header.len()].join("|")));
+    for row in rows {
+        let cells = row.split(',').collect::<Vec<&str>>().join(" | ");
+        out.push_str(&format!("| {} |\n", cells));
+    }
+    out
+}
+
+/// Append a bare line to `GITHUB_PATH`, used for `add-path`.
+fn append_path_file<T: AsRef<str>>(path: T, value: &str) -> Result<(), Box<dyn Error>> {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path.as_ref())?;
+    writeln!(file, "{}", value)?;
+    Ok(())
+}
+
 fn escape_data<T: AsRef<str>>(s: T) -> String {
     s.as_ref()
         .replace("%", "%25")
@@ -245,6 +551,45 @@ where
     issue_command(command, message, params)
 }
 
+/// Like `log_command`, but also supports the `endLine`/`endColumn`/`title` properties that the
+/// `notice` annotation accepts on top of `file`/`line`/`col`.
+#[allow(clippy::too_many_arguments)]
+fn annotate_command<T, U>(
+    command: T,
+    message: U,
+    file: Option<String>,
+    line: Option<u64>,
+    col: Option<u64>,
+    end_line: Option<u64>,
+    end_column: Option<u64>,
+    title: Option<String>,
+) -> String
+where
+    T: AsRef<str>,
+    U: AsRef<str>,
+{
+    let mut params = Vec::new();
+    if let Some(file) = file {
+        params.push(("file".to_owned(), file))
+    }
+    if let Some(line) = line {
+        params.push(("line".to_owned(), format!("{}", line)))
+    }
+    if let Some(col) = col {
+        params.push(("col".to_owned(), format!("{}", col)))
+    }
+    if let Some(end_line) = end_line {
+        params.push(("endLine".to_owned(), format!("{}", end_line)))
+    }
+    if let Some(end_column) = end_column {
+        params.push(("endColumn".to_owned(), format!("{}", end_column)))
+    }
+    if let Some(title) = title {
+        params.push(("title".to_owned(), title))
+    }
+    issue_command(command, message, params)
+}
+
 fn issue<T, U>(command: T, message: U) -> String
 where
     T: AsRef<str>,
@@ -256,7 +601,28 @@ where
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let opt = Opt::from_args();
 
-    let out = match opt.command {
+    match opt.command {
+        Command::Batch => run_batch(),
+        command => {
+            let should_exit_failed = matches!(&command, Command::SetFailed { .. });
+            let out = dispatch(command, false)?;
+            println!("{}", out);
+            if should_exit_failed {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Run a single parsed `Command` and return the line it would print.
+///
+/// `in_batch` is `true` when `command` came from a `batch` line rather than the top-level argv;
+/// it's used to reject subcommand behavior that would otherwise consume the rest of the batch's
+/// stdin stream.
+fn dispatch(command: Command, in_batch: bool) -> Result<String, Box<dyn Error>> {
+    let out = match command {
+        Command::Batch => unreachable!("Batch is handled by run_batch before dispatch is called"),
         Command::IssueCommand {
             command,
             message,
@@ -265,24 +631,53 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             Some(message) => issue_command(&command[..], message, properties),
             None => issue_command(&command[..], "", properties),
         },
-        Command::SetEnv { key, value } => {
-            issue_command("set-env", value, vec![("name".to_owned(), key)])
-        }
+        Command::SetEnv { key, value } => match env::var("GITHUB_ENV") {
+            Ok(env_file) => {
+                write_env_file(env_file, &key, &value)?;
+                String::new()
+            }
+            Err(_) => issue_command("set-env", value, vec![("name".to_owned(), key)]),
+        },
         Command::Export { key } => {
             let val = std::env::var(key.clone())?;
             issue_command("set-env", val, vec![("name".to_owned(), key)])
         }
-        Command::SetOutput { name, value } => {
-            issue_command("set-output", value, vec![("name".to_owned(), name)])
-        }
+        Command::SetOutput { name, value } => match env::var("GITHUB_OUTPUT") {
+            Ok(output_file) => {
+                write_env_file(output_file, &name, &value)?;
+                String::new()
+            }
+            Err(_) => issue_command("set-output", value, vec![("name".to_owned(), name)]),
+        },
         Command::AddPath { path } => {
             let path = std::fs::canonicalize(path)?;
-            issue("add-path", path.to_string_lossy().into_owned())
+            let path = path.to_string_lossy().into_owned();
+            match env::var("GITHUB_PATH") {
+                Ok(path_file) => {
+                    append_path_file(path_file, &path)?;
+                    String::new()
+                }
+                Err(_) => issue("add-path", path),
+            }
         }
+        Command::Notice {
+            message,
+            file,
+            line,
+            col,
+            end_line,
+            end_column,
+            title,
+        } => annotate_command("notice", message, file, line, col, end_line, end_column, title),
         Command::AddMask { value } => issue("add-mask", value),
-        Command::GetInput { name, required } => {
+        Command::GetInput {
+            name,
+            required,
+            as_bool,
+            multiline,
+        } => {
             let key = format!("INPUT_{}", name.replace(" ", "_").to_ascii_uppercase());
-            match std::env::var(key) {
+            let val = match std::env::var(key) {
                 Ok(val) => val.trim().to_owned(),
                 Err(e) => {
                     if required {
@@ -291,6 +686,26 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         "".to_owned()
                     }
                 }
+            };
+            if as_bool {
+                match val.as_str() {
+                    "true" | "True" | "TRUE" => "true".to_owned(),
+                    "false" | "False" | "FALSE" => "false".to_owned(),
+                    other => {
+                        return Err(format!(
+                            "Input does not meet YAML 1.2 \"Core Schema\" specification: {}\nSupport boolean input list: `true | True | TRUE | false | False | FALSE`",
+                            other
+                        )
+                        .into())
+                    }
+                }
+            } else if multiline {
+                val.split('\n')
+                    .filter(|line| !line.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            } else {
+                val
             }
         }
         Command::IsDebug => std::env::var("RUNNER_DEBUG")?,
@@ -315,9 +730,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         Command::StopCommands { endtoken } => issue("stop-commands", endtoken),
         Command::StartGroup { name } => issue("group", name),
         Command::EndGroup => issue("endgroup", "".to_owned()),
-        Command::SaveState { name, value } => {
-            issue_command("save-state", value, vec![("name".to_owned(), name)])
-        }
+        Command::SaveState { name, value } => match env::var("GITHUB_STATE") {
+            Ok(state_file) => {
+                write_env_file(state_file, &name, &value)?;
+                String::new()
+            }
+            Err(_) => issue_command("save-state", value, vec![("name".to_owned(), name)]),
+        },
         Command::GetState { name } => {
             let key = format!("STATE_{}", name);
             match std::env::var(key) {
@@ -325,12 +744,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 Err(_) => "".to_owned(),
             }
         }
-        Command::PostComment { message, secret } => {
-            let client = reqwest::blocking::Client::new();
-            let github_event_path = env::var("GITHUB_EVENT_PATH")?;
-            let v: Value = serde_json::from_str(&github_event_path)?;
-            let uri = v["pull_request"]["comments_url"].as_str();
-
+        Command::PostComment {
+            message,
+            secret,
+            marker,
+            login,
+        } => {
             fn construct_headers() -> HeaderMap {
                 let mut headers = HeaderMap::new();
                 headers.insert(USER_AGENT, HeaderValue::from_static("action-cli"));
@@ -338,15 +757,337 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 headers
             }
 
-            let _res = client.post(uri.unwrap_or(""))
-                .headers(construct_headers())
-                .bearer_auth(secret)
-                .body(message)
-                .send()?;
-            issue("post-comment", "success".to_owned())
+            let token = env::var(&secret)?;
+            let client = reqwest::blocking::Client::new();
+            let github_event_path = env::var("GITHUB_EVENT_PATH")?;
+            let event_contents = std::fs::read_to_string(&github_event_path)?;
+            let event: Value = serde_json::from_str(&event_contents)?;
+
+            let comments_url = event["pull_request"]["comments_url"]
+                .as_str()
+                .or_else(|| event["issue"]["comments_url"].as_str())
+                .ok_or("event payload has no pull_request.comments_url or issue.comments_url")?
+                .to_owned();
+
+            let body = match &marker {
+                Some(marker) => format!("{}\n\n<!-- {} -->", message, marker),
+                None => message,
+            };
+
+            let existing = match &marker {
+                Some(marker) => {
+                    let comments_res = client
+                        .get(&comments_url)
+                        .headers(construct_headers())
+                        .bearer_auth(&token)
+                        .send()?
+                        .text()?;
+                    let comments: Vec<Value> = serde_json::from_str(&comments_res)?;
+
+                    comments.into_iter().find(|comment| {
+                        comment["user"]["login"].as_str() == Some(login.as_str())
+                            && comment["body"]
+                                .as_str()
+                                .is_some_and(|b| b.contains(marker.as_str()))
+                    })
+                }
+                None => None,
+            };
+
+            let payload = serde_json::to_string(&serde_json::json!({ "body": body }))?;
+
+            match existing {
+                Some(comment) => {
+                    let url = comment["url"].as_str().unwrap_or("").to_owned();
+                    client
+                        .patch(&url)
+                        .headers(construct_headers())
+                        .bearer_auth(&token)
+                        .body(payload)
+                        .send()?;
+                }
+                None => {
+                    client
+                        .post(&comments_url)
+                        .headers(construct_headers())
+                        .bearer_auth(&token)
+                        .body(payload)
+                        .send()?;
+                }
+            }
+
+            issue("post-comment", "success")
+        }
+        Command::LoadEnv { file } => {
+            let contents = std::fs::read_to_string(&file)?;
+            let env_file = env::var("GITHUB_ENV")?;
+            for (key, value) in parse_dotenv(&contents)? {
+                write_env_file(&env_file, &key, &value)?;
+            }
+            String::new()
+        }
+        Command::SetFailed { message } => issue("error", message),
+        Command::Summary {
+            body,
+            file,
+            stdin,
+            overwrite,
+            heading,
+            header,
+            row,
+        } => {
+            let content = if !header.is_empty() {
+                render_markdown_table(&header, &row)
+            } else {
+                let text = match (body, file, stdin) {
+                    (Some(body), _, _) => body,
+                    (None, Some(file), _) => std::fs::read_to_string(file)?,
+                    (None, None, true) if in_batch => {
+                        return Err(
+                            "summary --stdin is not supported inside batch: it would read to \
+                             stdin's EOF and consume the rest of the batch"
+                                .into(),
+                        )
+                    }
+                    (None, None, true) => {
+                        use std::io::Read;
+                        let mut buf = String::new();
+                        std::io::stdin().read_to_string(&mut buf)?;
+                        buf
+                    }
+                    (None, None, false) => {
+                        return Err("summary requires a body argument, --file, or --stdin".into())
+                    }
+                };
+                match heading {
+                    Some(level) => format!(
+                        "{} {}",
+                        "#".repeat(level.clamp(1, 6) as usize),
+                        text.trim_end()
+                    ),
+                    None => text,
+                }
+            };
+
+            use std::io::Write;
+            let summary_file = env::var("GITHUB_STEP_SUMMARY")?;
+            let mut file = if overwrite {
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .write(true)
+                    .truncate(true)
+                    .open(summary_file)?
+            } else {
+                std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(summary_file)?
+            };
+            writeln!(file, "{}", content)?;
+
+            String::new()
         }
     };
 
-    println!("{}", out);
+    Ok(out)
+}
+
+/// Execute the `batch` subcommand: read newline-delimited command invocations from stdin,
+/// dispatching each one in turn and printing its output line. `stop-commands` and `add-mask`
+/// state is tracked here so it carries across lines within the batch.
+///
+/// A single line panicking (e.g. a pre-existing non-graceful path in some subcommand) is caught
+/// and turned into a line-numbered error that ends the batch like any other failing line; the
+/// default panic hook is suppressed for the duration so a caught panic doesn't also dump a
+/// backtrace.
+fn run_batch() -> Result<(), Box<dyn std::error::Error>> {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(|_| {}));
+    let result = run_batch_inner();
+    std::panic::set_hook(default_hook);
+    result
+}
+
+fn run_batch_inner() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::{self, BufRead, Write};
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    let mut stopped_token: Option<String> = None;
+    let mut masks: Vec<String> = Vec::new();
+    let mut line_no = 0;
+
+    loop {
+        // Lock stdin only long enough to read one line, then drop the lock before dispatching,
+        // so no stdin lock is held while a line's command runs.
+        let mut line = String::new();
+        let bytes_read = {
+            let stdin = io::stdin();
+            let mut locked = stdin.lock();
+            locked.read_line(&mut line)?
+        };
+        if bytes_read == 0 {
+            break;
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        line_no += 1;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let parsed = split_command_line(&line).ok().and_then(|tokens| {
+            let mut argv = vec!["action-cli".to_owned()];
+            argv.extend(tokens);
+            Command::from_iter_safe(&argv).ok()
+        });
+
+        if let Some(token) = stopped_token.clone() {
+            let is_resume = is_resume_line(&token, &parsed);
+            if !is_resume {
+                // While commands are stopped, lines other than the resume line are never
+                // dispatched: they are logged verbatim, same as arbitrary script output would be.
+                writeln!(out, "{}", line)?;
+                continue;
+            }
+            stopped_token = None;
+        }
+
+        let command = parsed.ok_or_else(|| format!("line {}: not a recognized command: `{}`", line_no, line))?;
+
+        if let Command::Batch = &command {
+            return Err(format!("line {}: `batch` cannot be nested inside a batch", line_no).into());
+        }
+        if let Command::StopCommands { endtoken } = &command {
+            stopped_token = Some(endtoken.clone());
+        }
+        let new_mask = match &command {
+            Command::AddMask { value } => Some(value.clone()),
+            _ => None,
+        };
+        let should_exit_failed = matches!(&command, Command::SetFailed { .. });
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| dispatch(command, true)))
+            .map_err(|_| format!("line {}: command panicked", line_no))??;
+        writeln!(out, "{}", apply_masks(&result, &masks))?;
+        if let Some(mask) = new_mask {
+            masks.push(mask);
+        }
+
+        if should_exit_failed {
+            out.flush()?;
+            std::process::exit(1);
+        }
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dotenv_handles_plain_and_export_lines() {
+        let pairs = parse_dotenv("FOO=bar\nexport BAZ=qux\n# a comment\n\nQUUX=\n").unwrap();
+        assert_eq!(
+            pairs,
+            vec![
+                ("FOO".to_owned(), "bar".to_owned()),
+                ("BAZ".to_owned(), "qux".to_owned()),
+                ("QUUX".to_owned(), "".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_dotenv_handles_quoting_and_escapes() {
+        let pairs = parse_dotenv("SINGLE='a b'\nDOUBLE=\"a\\nb\"\n").unwrap();
+        assert_eq!(
+            pairs,
+            vec![
+                ("SINGLE".to_owned(), "a b".to_owned()),
+                ("DOUBLE".to_owned(), "a\nb".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_dotenv_joins_backslash_continuations() {
+        let pairs = parse_dotenv("FOO=one \\\ntwo\n").unwrap();
+        assert_eq!(pairs, vec![("FOO".to_owned(), "one two".to_owned())]);
+    }
+
+    #[test]
+    fn parse_dotenv_rejects_missing_equals() {
+        assert!(parse_dotenv("NOT_A_PAIR\n").is_err());
+    }
+
+    #[test]
+    fn unescape_double_quoted_resolves_known_escapes() {
+        assert_eq!(unescape_double_quoted("a\\nb\\t\\\"c\\\\d"), "a\nb\t\"c\\d");
+    }
+
+    #[test]
+    fn unescape_double_quoted_keeps_unknown_escapes_verbatim() {
+        assert_eq!(unescape_double_quoted("a\\qb"), "a\\qb");
+    }
+
+    #[test]
+    fn split_command_line_handles_quotes_and_escapes() {
+        let tokens = split_command_line(r#"debug "hello world" 'single quoted' escaped\ space"#).unwrap();
+        assert_eq!(tokens, vec!["debug", "hello world", "single quoted", "escaped space"]);
+    }
+
+    #[test]
+    fn split_command_line_rejects_unterminated_quote() {
+        assert!(split_command_line(r#"debug "unterminated"#).is_err());
+    }
+
+    #[test]
+    fn apply_masks_redacts_every_registered_value() {
+        let masks = vec!["secret1".to_owned(), "".to_owned(), "secret2".to_owned()];
+        assert_eq!(
+            apply_masks("leaked secret1 and secret2 values", &masks),
+            "leaked *** and *** values"
+        );
+    }
+
+    #[test]
+    fn is_resume_line_matches_only_the_stopped_endtoken() {
+        let resume = Some(Command::IssueCommand {
+            properties: vec![],
+            command: "my-token".to_owned(),
+            message: None,
+        });
+        let other = Some(Command::IssueCommand {
+            properties: vec![],
+            command: "other-token".to_owned(),
+            message: None,
+        });
+        assert!(is_resume_line("my-token", &resume));
+        assert!(!is_resume_line("my-token", &other));
+        assert!(!is_resume_line("my-token", &None));
+    }
+
+    #[test]
+    fn summary_stdin_is_rejected_inside_batch() {
+        let command = Command::Summary {
+            body: None,
+            file: None,
+            stdin: true,
+            overwrite: false,
+            heading: None,
+            header: vec![],
+            row: vec![],
+        };
+        let err = dispatch(command, true).unwrap_err();
+        assert!(err.to_string().contains("batch"));
+    }
+}